@@ -0,0 +1,57 @@
+
+// Writes the current render out to a PNG at native `size x size` resolution,
+// independent of however large the on-screen texture happens to be scaled.
+
+use std::path::Path;
+
+use crate::mandelbrot::Escape;
+use crate::palette::Palette;
+use crate::render::RenderHandle;
+
+/// Saves a `size x size` render to `path`, colouring each pixel by looking
+/// its escape time up through `escape`. Backend-agnostic so both the CPU
+/// render task and the GPU renderer's plain `Vec<Vec<_>>` can share it.
+fn save_png(
+    size: usize,
+    palette: Palette,
+    max_iter: u64,
+    path: &Path,
+    escape: impl Fn(u32, u32) -> Option<Escape>,
+) -> image::ImageResult<()> {
+    let image = image::RgbImage::from_fn(size as u32, size as u32, |x, y| {
+        image::Rgb(palette.color(escape(x, y), max_iter))
+    });
+
+    image.save(path)
+}
+
+/// Saves the CPU render task's current grid, snapshotting it under a single
+/// lock first so the export doesn't take the grid's `Mutex` once per pixel.
+pub fn save_png_cpu(
+    handle: &RenderHandle,
+    size: usize,
+    palette: Palette,
+    max_iter: u64,
+    path: &Path,
+) -> image::ImageResult<()> {
+    let snapshot = handle.snapshot();
+    save_png(size, palette, max_iter, path, |x, y| {
+        snapshot.escape(x as usize, y as usize, size)
+    })
+}
+
+/// Saves a GPU renderer's escape-time grid, as produced by `GpuRenderer::render`.
+pub fn save_png_gpu(
+    render: &[Vec<Option<std::num::NonZeroU64>>],
+    size: usize,
+    palette: Palette,
+    max_iter: u64,
+    path: &Path,
+) -> image::ImageResult<()> {
+    save_png(size, palette, max_iter, path, |x, y| {
+        render[y as usize][x as usize].map(|iterations| Escape {
+            iterations: iterations.get(),
+            magnitude: 0.,
+        })
+    })
+}
@@ -0,0 +1,245 @@
+
+// GPU compute-shader backend for the Mandelbrot kernel. Mirrors the escape
+// iteration reported by `Complex::compute_mandelbrot` (the shader writes 0 for
+// non-diverging points, which `render` maps to `None`) but dispatches one invocation per pixel on a wgpu compute pipeline instead of
+// walking the CPU thread pool.
+
+use std::num::NonZeroU64;
+
+use wgpu::util::DeviceExt;
+
+const SHADER_SRC: &str = include_str!("../shaders/mandelbrot.wgsl");
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: u32,
+    max_iter: u32,
+    row_offset: u32,
+    rows: u32,
+}
+
+pub struct GpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// The limits actually granted by `request_device`, used by `render` to
+    /// size each dispatch's output buffer within what the device can bind.
+    limits: wgpu::Limits,
+}
+
+impl GpuRenderer {
+    /// Tries to acquire a suitable adapter and build the compute pipeline.
+    /// Returns `None` when no adapter is available, so callers can fall back
+    /// to the CPU thread-pool path.
+    pub fn try_new() -> Option<GpuRenderer> {
+        let instance = wgpu::Instance::default();
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))?;
+
+        // `wgpu::Limits::default()` caps storage buffer bindings at 128 MiB,
+        // far under the ~1 GiB a single SIZE = 2^14 output buffer would need.
+        // Request whatever the adapter can actually grant instead; `render`
+        // bands its dispatch into chunks sized to whatever limit comes back,
+        // so even an adapter stuck on the portable defaults still works.
+        let required_limits = adapter.limits();
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("mandelbrot device"),
+                required_features: wgpu::Features::empty(),
+                required_limits,
+            },
+            None,
+        ))
+        .ok()?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mandelbrot shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER_SRC.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("mandelbrot bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("mandelbrot pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("mandelbrot pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        let limits = device.limits();
+
+        Some(GpuRenderer {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+            limits,
+        })
+    }
+
+    /// Renders an `size x size` escape-time grid over the `[-2, 2]` square,
+    /// as a row-major `Vec<Vec<Option<NonZeroU64>>>` indexed `[y][x]`, with
+    /// `None` for points that never diverged. Only the iteration count is
+    /// reported, not the final `|z|` magnitude.
+    ///
+    /// The full grid is banded into row chunks that each fit within the
+    /// device's granted `max_storage_buffer_binding_size`/`max_buffer_size`,
+    /// since at the default `SIZE = 2^14` a single output buffer for the
+    /// whole image would be ~1 GiB — well over what most adapters allow as
+    /// one binding.
+    pub fn render(&self, size: usize, max_iter: u32) -> Vec<Vec<Option<NonZeroU64>>> {
+        let bytes_per_row = size * std::mem::size_of::<u32>();
+        let max_binding_bytes = (self.limits.max_storage_buffer_binding_size as u64)
+            .min(self.limits.max_buffer_size) as usize;
+        let rows_per_chunk = (max_binding_bytes / bytes_per_row).clamp(1, size);
+
+        let mut rows = Vec::with_capacity(size);
+        let mut row_offset = 0;
+        while row_offset < size {
+            let chunk_rows = rows_per_chunk.min(size - row_offset);
+            rows.extend(self.render_rows(size, max_iter, row_offset as u32, chunk_rows as u32));
+            row_offset += chunk_rows;
+        }
+        rows
+    }
+
+    /// Dispatches and reads back `rows` rows of the image starting at
+    /// `row_offset`, as one self-contained compute pass with its own
+    /// output/staging buffers sized just for this chunk.
+    fn render_rows(
+        &self,
+        size: usize,
+        max_iter: u32,
+        row_offset: u32,
+        rows: u32,
+    ) -> Vec<Vec<Option<NonZeroU64>>> {
+        let pixel_count = size * rows as usize;
+
+        let params = Params {
+            size: size as u32,
+            max_iter,
+            row_offset,
+            rows,
+        };
+
+        let params_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mandelbrot params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mandelbrot output"),
+            size: (pixel_count * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mandelbrot staging"),
+            size: (pixel_count * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("mandelbrot bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: output_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("mandelbrot pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+
+            // one workgroup per 8x8 block of pixels, matching the shader's
+            // `@workgroup_size(8, 8)` declaration
+            let workgroups_x = (size as u32).div_ceil(8);
+            let workgroups_y = rows.div_ceil(8);
+            pass.dispatch_workgroups(workgroups_x, workgroups_y, 1);
+        }
+
+        encoder.copy_buffer_to_buffer(
+            &output_buffer,
+            0,
+            &staging_buffer,
+            0,
+            (pixel_count * std::mem::size_of::<u32>()) as u64,
+        );
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv().unwrap().unwrap();
+
+        let raw: Vec<u32> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+
+        raw.chunks(size)
+            .map(|row| {
+                row.iter()
+                    .map(|&escape_time| NonZeroU64::new(escape_time as u64))
+                    .collect()
+            })
+            .collect()
+    }
+}
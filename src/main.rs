@@ -1,201 +1,276 @@
 
-use std::{num::NonZeroU64, time::Instant};
+use std::path::Path;
+use std::time::Instant;
 
 use eframe::egui;
 
+mod mandelbrot;
+mod gpu;
+mod render;
+mod palette;
+mod export;
 
-#[derive(Clone, Copy)]
-struct Complex {
-    r: f64,
-    i: f64,
-}
+use gpu::GpuRenderer;
+use mandelbrot::Escape;
+use palette::Palette;
+use render::{ResultMsg, Viewport};
 
-impl std::ops::Add for Complex {
-    type Output = Complex;
 
-    #[inline]
-    fn add(self, rhs: Self) -> Self::Output {
-        Complex {
-            r: self.r + rhs.r,
-            i: self.i + rhs.i,
-        }
-    }
+fn color_for(escape: Option<Escape>, palette: Palette, max_iter: u64) -> egui::Color32 {
+    let [r, g, b] = palette.color(escape, max_iter);
+    egui::Color32::from_rgb(r, g, b)
 }
 
-impl Complex {
-    const ZERO: Self = Complex { r: 0., i: 0. };
+/// High-performance render of the fixed `[-2, 2]` square, used when a GPU
+/// adapter is available. Pan/zoom aren't supported here — the shader always
+/// covers the same square — but the iteration cap and palette are, the same
+/// as the CPU viewer's side panel. Changing the iteration cap re-dispatches
+/// the whole square on the GPU; changing the palette only recolours the
+/// existing render. Palettes are limited to `Palette::GPU_SUPPORTED`, since the compute
+/// shader only reports the escape iteration, not the final `|z|` smooth
+/// coloring needs.
+fn run_static_viewer(renderer: GpuRenderer, size: usize, max_iter: u64) {
+    let mut max_iter = max_iter;
+    let mut palette = Palette::Classic;
 
-    fn new(r: f64, i: f64) -> Complex {
-        Complex { r, i }
-    }
-
-    #[inline]
-    fn square(self) -> Complex {
-        Complex {
-            r: self.r * self.r - self.i * self.i,
-            i: 2. * self.r * self.i,
-        }
-    }
-
-    fn has_diverged(self) -> bool {
-        (self.r * self.r + self.i * self.i) > 4.
-    }
+    let start = Instant::now();
+    let mut render = renderer.render(size, max_iter as u32);
+    println!("done in {:#?}", start.elapsed());
 
-    fn compute_mandelbrot(self) -> Option<NonZeroU64> {
-        let mut z = Complex::ZERO;
+    let mut texture: Option<egui::TextureHandle> = None;
+    let mut dirty = true;
 
-        for i in 1..500 {
-            z = z.square() + self;
+    let options = eframe::NativeOptions::default();
+    eframe::run_simple_native("ProgSoc 2023 Rust Ripoff", options, move |ctx, _frame| {
+        if dirty {
+            let mut image = egui::ColorImage::new([size, size], egui::Color32::WHITE);
+            let width = image.width();
+
+            for (y, row) in image.pixels.chunks_mut(width).enumerate() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    let escape = render[y][x].map(|iterations| Escape {
+                        iterations: iterations.get(),
+                        magnitude: 0.,
+                    });
+                    *pixel = color_for(escape, palette, max_iter);
+                }
+            }
 
-            if z.has_diverged() {
-                return Some(NonZeroU64::new(i).unwrap());
+            match &mut texture {
+                Some(texture) => texture.set(image, Default::default()),
+                None => texture = Some(ctx.load_texture("colour-square", image, Default::default())),
             }
+            dirty = false;
         }
 
-        None
-    }
-}
-
-
-fn main() {
-    const SIZE: usize = 2usize.pow(14);
-    let thread_count = num_cpus::get();
-
-    let log_interval = SIZE / 100;
-
-    let mut render = Box::new(vec![vec![None; SIZE]; SIZE]);
-
-    println!("spawning {} worker threads", thread_count);
-    let mut threads: Vec<_> = (0..thread_count).map(|_| {
-        let (tx_row, rx_row) = std::sync::mpsc::channel();
-        let (tx_result, rx_result) = std::sync::mpsc::channel();
+        egui::SidePanel::left("controls").show(ctx, |ui| {
+            let mut rerender = false;
 
-        (
-            tx_row,
-            rx_result,
-            std::thread::spawn(move || {
-                while let Ok(Some(y)) = rx_row.recv() {
-                    let mut result = vec![None; SIZE];
+            ui.label("Max iterations");
+            let mut max_iter_slider = max_iter as u32;
+            if ui.add(egui::Slider::new(&mut max_iter_slider, 50..=5000)).changed() {
+                max_iter = max_iter_slider as u64;
+                rerender = true;
+            }
 
-                    for x in 0..SIZE {
-                        result[x] = Complex::new(
-                            (x as f64 / SIZE as f64) * 4. - 2.,
-                            (y as f64 / SIZE as f64) * 4. - 2.,
-                        ).compute_mandelbrot();
+            ui.label("Palette");
+            egui::ComboBox::from_id_source("palette")
+                .selected_text(palette.name())
+                .show_ui(ui, |ui| {
+                    for option in Palette::GPU_SUPPORTED {
+                        if ui.selectable_value(&mut palette, option, option.name()).changed() {
+                            dirty = true;
+                        }
                     }
+                });
 
-                    // println!("{}: completed {}", thread_num, y);
-                    tx_result.send(result).unwrap();
+            if ui.button("Save image").clicked() {
+                match export::save_png_gpu(&render, size, palette, max_iter, Path::new("mandelbrot.png")) {
+                    Ok(()) => println!("saved mandelbrot.png"),
+                    Err(err) => eprintln!("failed to save image: {err}"),
                 }
-            }),
-            None,
-        )
-    }).collect();
+            }
 
-    let mut next_row = 0;
+            if rerender {
+                let start = Instant::now();
+                render = renderer.render(size, max_iter as u32);
+                println!("done in {:#?}", start.elapsed());
+                dirty = true;
+            }
+        });
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().inner_margin(0.0))
+            .show(ctx, |ui| {
+                let Some(texture) = &texture else { return };
 
-    let mut completed_start_index = 0;
-    let mut completed = std::collections::VecDeque::new();
+                ui.centered_and_justified(|ui| {
+                    ui.image(texture, egui::Vec2::splat(ui.available_size().min_elem()));
+                });
+            });
+    }).unwrap();
+}
 
-    println!("starting");
-    let start = Instant::now();
-    loop {
-        // receive results from threads
-        for (
-            _,
-            rx,
-            _,
-            working
-        ) in threads.iter_mut() {
-            match rx.try_recv() {
-                Err(std::sync::mpsc::TryRecvError::Disconnected) => panic!("worker thread disconnected"),
-                Err(std::sync::mpsc::TryRecvError::Empty) => (),
-                Ok(result) => {
-                    let row: usize = working.take().unwrap();
-                    render[row] = result;
-
-                    // add completed rows to memory
-                    loop {
-                        match completed.get_mut(row - completed_start_index) {
-                            None => completed.push_back(false),
-                            Some(entry) => {
-                                *entry = true;
-                                break entry;
-                            },
-                        }
-                    };
+/// Interactive fractal viewer backed by the long-lived CPU render task in
+/// `render`. Mouse drags pan the viewport and the scroll wheel zooms it,
+/// the side panel exposes the iteration cap and palette, and "Save image"
+/// exports the current render at native resolution.
+fn run_interactive_viewer(size: usize, thread_count: usize) {
+    // `spawn` already starts generation 0 rendering `Viewport::DEFAULT`, so
+    // there's no need to (redundantly, wastefully) request it again here.
+    let handle = render::spawn(size, thread_count);
+
+    let mut viewport = Viewport::DEFAULT;
+    let mut palette = Palette::Classic;
+    let mut image = egui::ColorImage::new([size, size], egui::Color32::BLACK);
+    let mut texture: Option<egui::TextureHandle> = None;
+    // Pending tile updates, blitted in with `set_partial` instead of
+    // re-uploading the full `size x size` image on every completed tile.
+    let mut patches: Vec<([usize; 2], egui::ColorImage)> = Vec::new();
+    let mut dirty = true;
+    let mut repaint_all = false;
+    let mut progress: Option<(usize, usize)> = Some((0, 1));
 
-                    // clear memory
-                    while let Some(true) = completed.front() {
-                        completed.pop_front();
-                        completed_start_index += 1;
+    let options = eframe::NativeOptions::default();
+    eframe::run_simple_native("ProgSoc 2023 Rust Ripoff", options, move |ctx, _frame| {
+        while let Ok(msg) = handle.rx.try_recv() {
+            match msg {
+                ResultMsg::Tile { x, y, w, h } => {
+                    let mut patch = egui::ColorImage::new([w, h], egui::Color32::BLACK);
+                    for ty in 0..h {
+                        for tx in 0..w {
+                            let (px, py) = (x + tx, y + ty);
+                            let color = color_for(handle.escape(px, py, size), palette, viewport.max_iter);
+                            image.pixels[py * size + px] = color;
+                            patch.pixels[ty * w + tx] = color;
+                        }
                     }
+                    patches.push(([x, y], patch));
                 }
+                ResultMsg::ProgressReport { tiles_done, tiles_total } => {
+                    progress = Some((tiles_done, tiles_total));
+                }
+                ResultMsg::Finished => progress = None,
             }
         }
 
-        // dispatch work to threads
-        threads.retain_mut(|(
-            tx,
-            _,
-            _,
-            working
-        )| {
-            if working.is_some() {
-                true
-            } else {
-                if next_row < SIZE {
-                    tx.send(Some(next_row)).unwrap();
-                    *working = Some(next_row);
-                    next_row += 1;
-                    if next_row % log_interval == 0 {
-                        println!("done {} / {} rows", next_row, SIZE);
-                    }
-                    true
-                } else {
-                    tx.send(None).unwrap();
-                    false
+        if repaint_all {
+            let snapshot = handle.snapshot();
+            for y in 0..size {
+                for x in 0..size {
+                    image.pixels[y * size + x] =
+                        color_for(snapshot.escape(x, y, size), palette, viewport.max_iter);
                 }
             }
-        });
+            // the full reupload below covers every pixel, so any tile patches
+            // still pending from before the palette change are now redundant
+            patches.clear();
+            dirty = true;
+            repaint_all = false;
+        }
 
-        // break when done
-        if completed_start_index == SIZE {
-            break;
+        if dirty {
+            texture
+                .get_or_insert_with(|| ctx.load_texture("colour-square", image.clone(), Default::default()))
+                .set(image.clone(), Default::default());
+            patches.clear();
+            dirty = false;
+        } else if let Some(texture) = &mut texture {
+            for (pos, patch) in patches.drain(..) {
+                texture.set_partial(pos, patch, Default::default());
+            }
         }
-    }
 
-    println!("done in {:#?}", start.elapsed());
+        egui::SidePanel::left("controls").show(ctx, |ui| {
+            let mut rerender = false;
+
+            ui.label("Max iterations");
+            let mut max_iter = viewport.max_iter as u32;
+            if ui.add(egui::Slider::new(&mut max_iter, 50..=5000)).changed() {
+                viewport.max_iter = max_iter as u64;
+                rerender = true;
+            }
 
+            ui.label("Palette");
+            egui::ComboBox::from_id_source("palette")
+                .selected_text(palette.name())
+                .show_ui(ui, |ui| {
+                    for option in Palette::ALL {
+                        if ui.selectable_value(&mut palette, option, option.name()).changed() {
+                            repaint_all = true;
+                        }
+                    }
+                });
 
-    let mut texture: Option<egui::TextureHandle> = None;
+            if ui.button("Save image").clicked() {
+                match export::save_png_cpu(&handle, size, palette, viewport.max_iter, Path::new("mandelbrot.png")) {
+                    Ok(()) => println!("saved mandelbrot.png"),
+                    Err(err) => eprintln!("failed to save image: {err}"),
+                }
+            }
+
+            if rerender {
+                handle.tx.send(viewport).ok();
+                progress = Some((0, 1));
+            }
+        });
 
-    let options = eframe::NativeOptions::default();
-    eframe::run_simple_native("ProgSoc 2023 Rust Ripoff", options, move |ctx, _frame| {
         egui::CentralPanel::default()
             .frame(egui::Frame::none().inner_margin(0.0))
             .show(ctx, |ui| {
+                let Some(texture) = &texture else { return };
+
+                if let Some((tiles_done, tiles_total)) = progress {
+                    let fraction = tiles_done as f32 / tiles_total as f32;
+                    ui.add(
+                        egui::ProgressBar::new(fraction)
+                            .text(format!("{:.0}%", fraction * 100.)),
+                    );
+                }
 
-                let texture = texture.get_or_insert_with(|| {
-                    let mut image = egui::ColorImage::new([SIZE, SIZE], egui::Color32::WHITE);
-                    let width = image.width();
-
-                    for (y, row) in image.pixels.chunks_mut(width).enumerate() {
-                        for (x, pixel) in row.iter_mut().enumerate() {
-                            if let Some(escape_time) = render[y][x] {
-                                *pixel = egui::Color32::from_rgb(((Into::<u64>::into(escape_time) - 1) * 2u64.pow(5) % u8::MAX as u64) as u8, 0, 0);
-                            } else {
-                                *pixel = egui::Color32::BLACK
-                            }
-                        }
-                    }
+                let response = ui.centered_and_justified(|ui| {
+                    ui.image(texture, egui::Vec2::splat(ui.available_size().min_elem()))
+                }).inner;
 
-                    ctx.load_texture("colour-square", image, Default::default())
-                });
+                let response = ui.interact(response.rect, response.id, egui::Sense::drag());
+                let mut changed = false;
 
-                ui.centered_and_justified(|ui| {
-                    ui.image(texture, egui::Vec2::splat(ui.available_size().min_elem()));
-                });
+                if response.dragged() {
+                    let delta = response.drag_delta();
+                    let per_pixel = (viewport.scale * 2.) / response.rect.width() as f64;
+                    viewport.center.r -= delta.x as f64 * per_pixel;
+                    viewport.center.i -= delta.y as f64 * per_pixel;
+                    changed = true;
+                }
+
+                let scroll = ctx.input(|i| i.scroll_delta.y);
+                if scroll != 0. {
+                    viewport.scale *= (1. - scroll as f64 * 0.001).clamp(0.1, 10.);
+                    changed = true;
+                }
+
+                if changed {
+                    handle.tx.send(viewport).ok();
+                    // the render task reports the real tile count moments later
+                    progress = Some((0, 1));
+                }
             });
     }).unwrap();
 }
+
+
+fn main() {
+    const SIZE: usize = 2usize.pow(14);
+    const MAX_ITER: u64 = 500;
+
+    match GpuRenderer::try_new() {
+        Some(renderer) => {
+            println!("using GPU backend");
+            run_static_viewer(renderer, SIZE, MAX_ITER);
+        }
+        None => {
+            println!("no suitable GPU adapter found, using interactive CPU backend");
+            run_interactive_viewer(SIZE, num_cpus::get());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+
+/// The point at which a sequence escaped the Mandelbrot set: the iteration
+/// count it took, plus the magnitude of `z` at that point. The magnitude is
+/// only needed for smooth/continuous coloring, but is cheap enough to always
+/// return.
+#[derive(Clone, Copy)]
+pub struct Escape {
+    pub iterations: u64,
+    pub magnitude: f64,
+}
+
+#[derive(Clone, Copy)]
+pub struct Complex {
+    pub r: f64,
+    pub i: f64,
+}
+
+impl std::ops::Add for Complex {
+    type Output = Complex;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex {
+            r: self.r + rhs.r,
+            i: self.i + rhs.i,
+        }
+    }
+}
+
+impl Complex {
+    pub const ZERO: Self = Complex { r: 0., i: 0. };
+
+    pub fn new(r: f64, i: f64) -> Complex {
+        Complex { r, i }
+    }
+
+    #[inline]
+    fn square(self) -> Complex {
+        Complex {
+            r: self.r * self.r - self.i * self.i,
+            i: 2. * self.r * self.i,
+        }
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.r * self.r + self.i * self.i
+    }
+
+    fn has_diverged(self) -> bool {
+        self.norm_sqr() > 4.
+    }
+
+    pub fn compute_mandelbrot(self, max_iter: u64) -> Option<Escape> {
+        let mut z = Complex::ZERO;
+
+        for i in 1..max_iter {
+            z = z.square() + self;
+
+            if z.has_diverged() {
+                return Some(Escape {
+                    iterations: i,
+                    magnitude: z.norm_sqr().sqrt(),
+                });
+            }
+        }
+
+        None
+    }
+}
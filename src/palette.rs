@@ -0,0 +1,68 @@
+
+// Maps an escape-time result to an RGB colour. Kept independent of egui so
+// it can be reused by both the live viewer and the PNG exporter.
+
+use crate::mandelbrot::Escape;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    /// Hard-banded rainbow gradient keyed directly off the integer
+    /// iteration count, so colour bands show visible stairstep edges.
+    Classic,
+    /// Iteration count mapped linearly onto a grey ramp.
+    Grayscale,
+    /// Continuous rainbow gradient keyed off the fractional escape time, so
+    /// bands blend smoothly instead of stairstepping.
+    Smooth,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Classic, Palette::Grayscale, Palette::Smooth];
+
+    /// Palettes that only need the iteration count, for backends (the GPU
+    /// compute shader) that don't report the `|z|` magnitude `Smooth` needs.
+    pub const GPU_SUPPORTED: [Palette; 2] = [Palette::Classic, Palette::Grayscale];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::Grayscale => "Grayscale",
+            Palette::Smooth => "Smooth",
+        }
+    }
+
+    /// Colours a pixel. `max_iter` is the iteration cap the escape time was
+    /// computed with, used to normalise the grayscale ramp.
+    pub fn color(self, escape: Option<Escape>, max_iter: u64) -> [u8; 3] {
+        let Some(escape) = escape else {
+            return [0, 0, 0];
+        };
+
+        match self {
+            Palette::Classic => hue_to_rgb(escape.iterations as f64 * 0.02),
+            Palette::Grayscale => {
+                let t = (escape.iterations as f64 / max_iter as f64 * 255.) as u8;
+                [t, t, t]
+            }
+            Palette::Smooth => {
+                // i + 1 - log2(log2(|z|)): a fractional escape time, so
+                // adjacent bands blend instead of stairstepping
+                let smoothed = escape.iterations as f64 + 1. - escape.magnitude.log2().log2();
+                hue_to_rgb(smoothed * 0.02)
+            }
+        }
+    }
+}
+
+/// A simple phase-shifted cosine rainbow, sampled at `t` (wraps every 1.0).
+fn hue_to_rgb(t: f64) -> [u8; 3] {
+    let channel = |phase: f64| {
+        (0.5 + 0.5 * (std::f64::consts::TAU * (t + phase)).cos()) * 255.
+    };
+
+    [
+        channel(0.) as u8,
+        channel(1. / 3.) as u8,
+        channel(2. / 3.) as u8,
+    ]
+}
@@ -0,0 +1,330 @@
+
+// Long-lived render task driving the interactive CPU viewer. Owns a pool of
+// worker threads and a tile-based work queue, and reacts to viewport changes
+// sent from the UI.
+//
+// Work is handed out as 64x64 pixel tiles rather than whole rows: workers
+// claim the next tile with a single atomic fetch-add instead of waiting on a
+// per-row request/response channel, and write their pixels directly into a
+// shared grid of atomics rather than shipping row data back over a channel.
+// This balances load between the cheap exterior of the set and the
+// expensive interior far better than one-row-per-worker did, and means the
+// dispatcher never needs to busy-poll for results: workers that run out of
+// tiles just wait on a condition variable for the next generation.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::mandelbrot::{Complex, Escape};
+
+/// Side length of a work tile. Small enough to balance load across workers,
+/// large enough that claiming one is worth the atomic fetch-add.
+pub const TILE_SIZE: usize = 64;
+
+/// The portion of the complex plane currently on screen: a square centered
+/// on `center` spanning `[-scale, scale]` along each axis, along with the
+/// iteration cap to render it at.
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub center: Complex,
+    pub scale: f64,
+    pub max_iter: u64,
+}
+
+impl Viewport {
+    pub const DEFAULT: Viewport = Viewport {
+        center: Complex::ZERO,
+        scale: 2.,
+        max_iter: 500,
+    };
+
+    /// Maps a pixel coordinate in an `size x size` image to its corresponding
+    /// point in the complex plane under this viewport.
+    fn point(&self, x: usize, y: usize, size: usize) -> Complex {
+        Complex::new(
+            self.center.r + ((x as f64 / size as f64) * 2. - 1.) * self.scale,
+            self.center.i + ((y as f64 / size as f64) * 2. - 1.) * self.scale,
+        )
+    }
+}
+
+/// Messages streamed back from the render task to the UI.
+pub enum ResultMsg {
+    /// A freshly completed tile; the UI re-reads its pixels from the shared
+    /// grid via `RenderHandle::escape` and blits them in.
+    Tile { x: usize, y: usize, w: usize, h: usize },
+    /// How many tiles of the current render have completed so far, so the UI
+    /// can draw a progress bar instead of freezing until `Finished`.
+    ProgressReport { tiles_done: usize, tiles_total: usize },
+    Finished,
+}
+
+/// The two atomics each pixel gets: the escape iteration (0 meaning "hasn't
+/// diverged", which doubles as "not yet computed" for display purposes), and
+/// the bit pattern of the `|z|` magnitude at that point, needed for smooth
+/// coloring.
+#[derive(Clone)]
+struct Grid {
+    iterations: Arc<[AtomicU64]>,
+    magnitude: Arc<[AtomicU64]>,
+}
+
+impl Grid {
+    fn new(size: usize) -> Grid {
+        Grid {
+            iterations: (0..size * size).map(|_| AtomicU64::new(0)).collect(),
+            magnitude: (0..size * size).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn store(&self, idx: usize, escape: Option<Escape>) {
+        let (iterations, magnitude) = match escape {
+            Some(escape) => (escape.iterations, escape.magnitude.to_bits()),
+            None => (0, 0),
+        };
+        self.iterations[idx].store(iterations, Ordering::Relaxed);
+        self.magnitude[idx].store(magnitude, Ordering::Relaxed);
+    }
+
+    fn load(&self, idx: usize) -> Option<Escape> {
+        let iterations = self.iterations[idx].load(Ordering::Relaxed);
+        if iterations == 0 {
+            return None;
+        }
+        let magnitude = f64::from_bits(self.magnitude[idx].load(Ordering::Relaxed));
+        Some(Escape { iterations, magnitude })
+    }
+}
+
+pub struct RenderHandle {
+    pub tx: Sender<Viewport>,
+    pub rx: Receiver<ResultMsg>,
+    grid: Arc<Mutex<Grid>>,
+}
+
+impl RenderHandle {
+    /// Reads a pixel out of the shared escape-time grid. `size` must match
+    /// the size the render task is currently producing.
+    pub fn escape(&self, x: usize, y: usize, size: usize) -> Option<Escape> {
+        self.grid.lock().unwrap().load(y * size + x)
+    }
+
+    /// Clones the grid's handle under a single lock. Cloning `Grid` only
+    /// clones its two `Arc`s, so this is cheap even at the default
+    /// `SIZE = 2^14` — unlike calling `escape` once per pixel, which takes
+    /// the `Mutex` on every call. Use this for full-image passes like a
+    /// palette repaint or a PNG export.
+    pub fn snapshot(&self) -> GridSnapshot {
+        GridSnapshot(self.grid.lock().unwrap().clone())
+    }
+}
+
+/// A lock-free view of the escape-time grid. It shares the live atomics
+/// rather than copying them, so pixels the render task is still writing can
+/// change while it is read. See `RenderHandle::snapshot`.
+pub struct GridSnapshot(Grid);
+
+impl GridSnapshot {
+    pub fn escape(&self, x: usize, y: usize, size: usize) -> Option<Escape> {
+        self.0.load(y * size + x)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Tile {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+}
+
+fn build_tiles(size: usize) -> Arc<[Tile]> {
+    let mut tiles = Vec::new();
+
+    let mut y = 0;
+    while y < size {
+        let h = TILE_SIZE.min(size - y);
+
+        let mut x = 0;
+        while x < size {
+            let w = TILE_SIZE.min(size - x);
+            tiles.push(Tile { x, y, w, h });
+            x += TILE_SIZE;
+        }
+
+        y += TILE_SIZE;
+    }
+
+    tiles.into()
+}
+
+/// One render's worth of shared state: a fixed tile list and the atomic
+/// counter workers use to claim tiles from it, plus the grid they write
+/// their results into.
+#[derive(Clone)]
+struct Generation {
+    id: usize,
+    viewport: Viewport,
+    size: usize,
+    tiles: Arc<[Tile]>,
+    next_tile: Arc<AtomicUsize>,
+    grid: Grid,
+}
+
+/// Tracks the newest generation id so idle workers can wait on it instead of
+/// spinning once they run out of tiles.
+struct GenerationId(Mutex<usize>, Condvar);
+
+/// Everything the dispatcher loop reacts to, merged onto one channel so it
+/// can block on a single `recv()` instead of polling two receivers.
+enum DispatchEvent {
+    Msg(Viewport),
+    TileDone(usize, Tile),
+}
+
+fn spawn_worker(
+    current: Arc<Mutex<Generation>>,
+    generation_id: Arc<GenerationId>,
+    event_tx: Sender<DispatchEvent>,
+) {
+    std::thread::spawn(move || loop {
+        let gen = current.lock().unwrap().clone();
+        let idx = gen.next_tile.fetch_add(1, Ordering::Relaxed);
+
+        if idx >= gen.tiles.len() {
+            // out of tiles for this generation; wait for a newer one instead
+            // of spinning on the queue
+            let guard = generation_id.0.lock().unwrap();
+            let _ = generation_id
+                .1
+                .wait_timeout_while(guard, Duration::from_millis(50), |id| *id == gen.id)
+                .unwrap();
+            continue;
+        }
+
+        let tile = gen.tiles[idx];
+
+        for ty in 0..tile.h {
+            for tx in 0..tile.w {
+                let (x, y) = (tile.x + tx, tile.y + ty);
+                let escape = gen
+                    .viewport
+                    .point(x, y, gen.size)
+                    .compute_mandelbrot(gen.viewport.max_iter);
+                gen.grid.store(y * gen.size + x, escape);
+            }
+        }
+
+        event_tx.send(DispatchEvent::TileDone(gen.id, tile)).ok();
+    });
+}
+
+/// Spawns the render task and returns a handle for sending viewport updates
+/// and receiving completion notifications. `initial_size` is the image side
+/// length; the task renders `Viewport::DEFAULT` immediately so the first
+/// frame has something to show.
+pub fn spawn(initial_size: usize, thread_count: usize) -> RenderHandle {
+    let (msg_tx, msg_rx) = mpsc::channel::<Viewport>();
+    let (result_tx, result_rx) = mpsc::channel::<ResultMsg>();
+    let (event_tx, event_rx) = mpsc::channel::<DispatchEvent>();
+
+    let grid = Grid::new(initial_size);
+    let shared_grid = Arc::new(Mutex::new(grid.clone()));
+
+    let first = Generation {
+        id: 0,
+        viewport: Viewport::DEFAULT,
+        size: initial_size,
+        tiles: build_tiles(initial_size),
+        next_tile: Arc::new(AtomicUsize::new(0)),
+        grid,
+    };
+    let mut tiles_total = first.tiles.len();
+
+    let current = Arc::new(Mutex::new(first));
+    let generation_id = Arc::new(GenerationId(Mutex::new(0), Condvar::new()));
+
+    for _ in 0..thread_count {
+        spawn_worker(current.clone(), generation_id.clone(), event_tx.clone());
+    }
+
+    // forwards viewport updates onto the same event channel workers report
+    // tile completions on, so the dispatcher loop below can block on a
+    // single receiver instead of polling two and spinning
+    {
+        let event_tx = event_tx.clone();
+        std::thread::spawn(move || {
+            while let Ok(msg) = msg_rx.recv() {
+                if event_tx.send(DispatchEvent::Msg(msg)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    std::thread::spawn(move || {
+        let mut next_id = 1;
+        let mut tiles_done = 0;
+
+        for event in event_rx {
+            match event {
+                DispatchEvent::Msg(viewport) => {
+                    let previous = current.lock().unwrap().clone();
+                    let size = previous.size;
+
+                    let generation = Generation {
+                        id: next_id,
+                        viewport,
+                        size,
+                        tiles: build_tiles(size),
+                        next_tile: Arc::new(AtomicUsize::new(0)),
+                        grid: previous.grid.clone(),
+                    };
+                    tiles_total = generation.tiles.len();
+                    tiles_done = 0;
+
+                    *current.lock().unwrap() = generation;
+                    *generation_id.0.lock().unwrap() = next_id;
+                    generation_id.1.notify_all();
+
+                    result_tx
+                        .send(ResultMsg::ProgressReport { tiles_done, tiles_total })
+                        .ok();
+
+                    next_id += 1;
+                }
+                DispatchEvent::TileDone(gen_id, tile) => {
+                    if gen_id != next_id - 1 {
+                        // belongs to a generation that's since been superseded
+                        continue;
+                    }
+
+                    tiles_done += 1;
+                    result_tx
+                        .send(ResultMsg::Tile {
+                            x: tile.x,
+                            y: tile.y,
+                            w: tile.w,
+                            h: tile.h,
+                        })
+                        .ok();
+                    result_tx
+                        .send(ResultMsg::ProgressReport { tiles_done, tiles_total })
+                        .ok();
+
+                    if tiles_done == tiles_total {
+                        result_tx.send(ResultMsg::Finished).ok();
+                    }
+                }
+            }
+        }
+    });
+
+    RenderHandle {
+        tx: msg_tx,
+        rx: result_rx,
+        grid: shared_grid,
+    }
+}